@@ -1,30 +1,52 @@
-use anyhow::Result;
+use futures::{Stream, StreamExt};
 use reqwest::{
     header::{self, ACCEPT, ACCEPT_LANGUAGE, CONTENT_TYPE, COOKIE, HOST, REFERER, USER_AGENT},
     multipart, Body, Method, Proxy,
 };
-use serde_json::json;
+use secrecy::{ExposeSecret, Secret};
 
 use crate::{
+    config::RequestConfig,
+    error::{Claude2Error, Result},
     objects::{Conversation, History},
-    utils::request,
+    options::SendOptions,
+    shared,
+    utils::{request, request_stream},
 };
 
 pub struct Client {
-    pub cookie: String,
+    pub cookie: Secret<String>,
     pub proxys: Vec<Proxy>,
     pub organization_id: String,
+    pub send_options: SendOptions,
+    pub request_config: RequestConfig,
     base_header: header::HeaderMap,
 }
 
+impl std::fmt::Debug for Client {
+    // redact the cookie (and skip `base_header`, which also carries it) so
+    // an accidental `{:?}` logging call never leaks the session credential.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("cookie", &self.cookie)
+            .field("proxys", &self.proxys)
+            .field("organization_id", &self.organization_id)
+            .field("send_options", &self.send_options)
+            .field("request_config", &self.request_config)
+            .finish()
+    }
+}
+
 impl Client {
     // create a new client, must provide cookie
     // if specific proxies, this method will check its availability(scheme)
     // supported proxies(by reqwest): http, https, socks5
     pub async fn try_new(cookie: &str, proxys: Vec<String>) -> Result<Self> {
+        let cookie = Secret::new(cookie.to_owned());
+
         let mut headers: header::HeaderMap = header::HeaderMap::new();
         headers.insert(HOST, "claude.ai".parse()?);
-        headers.insert(COOKIE, cookie.parse()?);
+        headers.insert(COOKIE, cookie.expose_secret().parse()?);
         headers.insert(REFERER, "https://claude.ai/chats".parse()?);
         headers.insert(CONTENT_TYPE, "application/json".parse()?);
         headers.insert(ACCEPT, "*/*".parse()?);
@@ -48,22 +70,32 @@ impl Client {
             }
         }
 
+        let request_config = RequestConfig::default();
+
         let organization_id = get_organization_id(
             &request(
                 Method::GET,
-                "https://claude.ai/api/organizations",
+                shared::ORGANIZATIONS_URL,
                 &reqwest_proxies,
                 headers.clone(),
                 None,
                 None,
+                &request_config,
             )
             .await?,
         )?;
 
+        let send_options = SendOptions {
+            timezone: iana_time_zone::get_timezone().unwrap_or_else(|_| "UTC".to_owned()),
+            ..SendOptions::default()
+        };
+
         Ok(Self {
-            cookie: cookie.to_owned(),
+            cookie,
             proxys: reqwest_proxies,
             organization_id: organization_id,
+            send_options,
+            request_config,
             base_header: headers,
         })
     }
@@ -80,16 +112,28 @@ impl Client {
             }
         }
     }
+
+    pub fn set_timeout(&mut self, timeout: std::time::Duration) {
+        self.request_config.timeout = timeout;
+    }
+
+    pub fn set_max_retries(&mut self, max_retries: u32) {
+        self.request_config.max_retries = max_retries;
+    }
+
+    pub fn set_base_backoff(&mut self, base_backoff: std::time::Duration) {
+        self.request_config.base_backoff = base_backoff;
+    }
 }
 
 fn get_organization_id(content: &[u8]) -> Result<String> {
     let response: serde_json::Value = serde_json::from_slice(&content)?;
     response
         .as_array()
-        .ok_or(anyhow::anyhow!("no organization info found"))?[0]
+        .ok_or_else(|| Claude2Error::UnexpectedFormat("no organization info found".to_owned()))?[0]
         .get("uuid")
         .map(|s| s.as_str().unwrap_or("").to_owned())
-        .ok_or(anyhow::anyhow!("no organization id found"))
+        .ok_or_else(|| Claude2Error::UnexpectedFormat("no organization id found".to_owned()))
 }
 
 impl Client {
@@ -99,18 +143,14 @@ impl Client {
     // 3. check the response status code, parse data in the right way
 
     pub async fn list_all_conversations(&self) -> Result<Vec<Conversation>> {
-        let conversations_url = format!(
-            "https://claude.ai/api/organizations/{}/chat_conversations",
-            self.organization_id
-        );
-
         let content = request(
             Method::GET,
-            &conversations_url,
+            &shared::chat_conversations_url(&self.organization_id),
             &self.proxys,
             self.base_header.clone(),
             None,
             None,
+            &self.request_config,
         )
         .await?;
 
@@ -127,42 +167,32 @@ impl Client {
 
         request(
             Method::POST,
-            &format!(
-                "https://claude.ai/api/organizations/{}/chat_conversations",
-                self.organization_id
-            ),
+            &shared::chat_conversations_url(&self.organization_id),
             &self.proxys,
             self.base_header.clone(),
-            Some(
-                json!({
-                    "uuid": id.clone(),
-                    "name": Self::NEW_CHAT_NAME,
-                })
-                .to_string(),
-            ),
+            Some(shared::create_chat_conversation_body(&id, Self::NEW_CHAT_NAME)),
             None,
+            &self.request_config,
         )
-        .await
-        .map(|_| id)
-        .map_err(|_e| anyhow::anyhow!("create chat conversation failed"))
+        .await?;
+
+        Ok(id)
     }
 
     // delete
     pub async fn delete_chat_conversation(&self, conversation_id: &str) -> Result<()> {
         request(
             Method::DELETE,
-            &format!(
-                "https://claude.ai/api/organizations/{}/chat_conversations/{}",
-                self.organization_id, conversation_id,
-            ),
+            &shared::chat_conversation_url(&self.organization_id, conversation_id),
             &self.proxys,
             self.base_header.clone(),
             None,
             None,
+            &self.request_config,
         )
-        .await
-        .map(|_| Ok(()))
-        .map_err(|_| anyhow::anyhow!("delete chat conversation failed"))?
+        .await?;
+
+        Ok(())
     }
 
     // modify
@@ -173,56 +203,63 @@ impl Client {
     ) -> Result<()> {
         request(
             Method::POST,
-            "https://claude.ai/api/rename_chat",
+            shared::RENAME_CHAT_URL,
             &self.proxys,
             self.base_header.clone(),
-            Some(
-                json!(
-                {
-                    "organization_uuid": self.organization_id,
-                    "conversation_uuid": conversation_id,
-                    "title": new_title
-                }
-                )
-                .to_string(),
-            ),
+            Some(shared::rename_chat_conversation_body(
+                &self.organization_id,
+                conversation_id,
+                new_title,
+            )),
             None,
+            &self.request_config,
         )
-        .await
-        .map(|_| Ok(()))
-        .map_err(|_| anyhow::anyhow!("rename chat conversation failed"))?
+        .await?;
+
+        Ok(())
     }
 
     // modify
     // upload attachment should accompany with a send_message request
     pub async fn upload_attachment(&self, filename: &str) -> Result<serde_json::Value> {
-        let file = tokio::fs::File::open(filename).await?;
+        let file = tokio::fs::File::open(filename).await.map_err(|e| {
+            Claude2Error::UnexpectedFormat(format!("failed to open {}: {}", filename, e))
+        })?;
+
+        let file_name = std::path::Path::new(filename)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(filename)
+            .to_owned();
+        let mime_type = mime_guess::from_path(filename).first_or_octet_stream();
 
         // read file body stream
         let stream = tokio_util::codec::FramedRead::new(file, tokio_util::codec::BytesCodec::new());
         let file_body = Body::wrap_stream(stream);
         let some_file = multipart::Part::stream(file_body)
-            .file_name("demo.pdf")
-            .mime_str("application/pdf")?;
+            .file_name(file_name)
+            .mime_str(mime_type.as_ref())?;
 
         let form = multipart::Form::new()
             .text("orgUuid", self.organization_id.clone())
             .part("file", some_file);
 
+        // the multipart form carries its own `Content-Type: multipart/
+        // form-data; boundary=...` header; the base header's `application/
+        // json` must not be sent in its place.
         let mut headers = self.base_header.clone();
-
-        headers.insert(CONTENT_TYPE, "multipart/form-data".parse().unwrap());
+        headers.remove(CONTENT_TYPE);
 
         let content = request(
             Method::POST,
-            "https://claude.ai/api/convert_document",
+            shared::CONVERT_DOCUMENT_URL,
             &self.proxys,
-            self.base_header.clone(),
+            headers,
             None,
             Some(form),
+            &self.request_config,
         )
-        .await
-        .map_err(|_| anyhow::anyhow!("rename file {} failed", filename))?;
+        .await?;
 
         Ok(serde_json::from_slice(&content)?)
     }
@@ -234,6 +271,7 @@ impl Client {
         conversation_id: &str,
         prompt: &str,
         attachment: Option<&str>,
+        options: Option<&SendOptions>,
     ) -> Result<serde_json::Value> {
         let attachments = if let Some(attachment) = attachment {
             let attachment = self.upload_attachment(attachment).await?;
@@ -241,67 +279,163 @@ impl Client {
         } else {
             vec![]
         };
-        request(
+        let bytes = request(
             Method::POST,
-            "https://claude.ai/api/append_message",
+            shared::APPEND_MESSAGE_URL,
             &self.proxys,
             self.base_header.clone(),
-            Some(
-                json!(
-                {
-                    "completion": {
-                        "prompt": prompt,
-                        "timezone": "Asia/Kolkata",
-                        "model": "claude-2"
-                    },
-                    "organization_uuid": self.organization_id,
-                    "conversation_uuid": conversation_id,
-                    "text": prompt,
-                    "attachments": attachments
-                }
-                )
-                .to_string(),
-            ),
+            Some(shared::send_message_body(
+                &self.organization_id,
+                conversation_id,
+                prompt,
+                &attachments,
+                options.unwrap_or(&self.send_options),
+            )),
             None,
+            &self.request_config,
         )
-        .await
-        .map(|bytes| {
-            let s = std::str::from_utf8(&bytes).unwrap();
-            let data = s
-                .trim()
-                .split("\n")
-                .last()
-                .ok_or(anyhow::anyhow!("wrong response format"))?;
-            let data = &data[6..];
-            let json = serde_json::from_str(data);
-
-            Ok(json?)
-        })
-        .map_err(|_| anyhow::anyhow!("send message failed"))?
+        .await?;
+
+        let s = std::str::from_utf8(&bytes)?;
+        let data = s
+            .trim()
+            .split('\n')
+            .last()
+            .ok_or_else(|| Claude2Error::UnexpectedFormat("empty response body".to_owned()))?;
+        let data = data
+            .strip_prefix("data: ")
+            .ok_or_else(|| Claude2Error::UnexpectedFormat(format!("unexpected line: {}", data)))?;
+
+        Ok(serde_json::from_str(data)?)
+    }
+
+    // modify
+    // same as `send_message`, but yields each incremental `completion` delta
+    // as soon as it arrives instead of waiting for the full response
+    pub async fn send_message_stream(
+        &self,
+        conversation_id: &str,
+        prompt: &str,
+        attachment: Option<&str>,
+        options: Option<&SendOptions>,
+    ) -> Result<impl Stream<Item = Result<String>>> {
+        let attachments = if let Some(attachment) = attachment {
+            let attachment = self.upload_attachment(attachment).await?;
+            vec![attachment.to_string()]
+        } else {
+            vec![]
+        };
+
+        let chunks = request_stream(
+            Method::POST,
+            shared::APPEND_MESSAGE_URL,
+            &self.proxys,
+            self.base_header.clone(),
+            Some(shared::send_message_body(
+                &self.organization_id,
+                conversation_id,
+                prompt,
+                &attachments,
+                options.unwrap_or(&self.send_options),
+            )),
+            &self.request_config,
+        )
+        .await?;
+
+        Ok(sse_completion_deltas(chunks))
     }
 
     // query
     pub async fn chat_conversation_history(&self, conversation_id: &str) -> Result<History> {
-        request(
+        let content = request(
             Method::GET,
-            &format!(
-                "https://claude.ai/api/organizations/{}/chat_conversations/{}",
-                self.organization_id, conversation_id,
-            ),
+            &shared::chat_conversation_url(&self.organization_id, conversation_id),
             &self.proxys,
             self.base_header.clone(),
             None,
             None,
+            &self.request_config,
         )
-        .await
-        .map(|bytes| Ok(serde_json::from_slice(&bytes)?))
-        .map_err(|_| anyhow::anyhow!("rename chat conversation failed"))?
+        .await?;
+
+        Ok(serde_json::from_slice(&content)?)
     }
 }
 
+// turns a `text/event-stream` body of `data: {json}\n\n` frames into a stream
+// of incremental `completion` deltas. a single network chunk may contain
+// several frames or split one mid-line, so we keep a rolling buffer and only
+// act on complete lines.
+fn sse_completion_deltas(
+    chunks: impl Stream<Item = reqwest::Result<bytes::Bytes>>,
+) -> impl Stream<Item = Result<String>> {
+    futures::stream::unfold(
+        (Box::pin(chunks), Vec::<u8>::new()),
+        |(mut chunks, mut buf)| async move {
+            loop {
+                if let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = buf.drain(..=pos).collect();
+                    let line = String::from_utf8_lossy(&line);
+                    let line = line.trim();
+
+                    // keep-alive / blank lines between events carry no data
+                    if line.is_empty() {
+                        continue;
+                    }
+                    let Some(data) = line.strip_prefix("data: ") else {
+                        continue;
+                    };
+
+                    return Some((parse_completion_delta(data), (chunks, buf)));
+                }
+
+                match chunks.next().await {
+                    Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+                    Some(Err(e)) => return Some((Err(e.into()), (chunks, buf))),
+                    None => return None,
+                }
+            }
+        },
+    )
+}
+
+fn parse_completion_delta(data: &str) -> Result<String> {
+    let value: serde_json::Value = serde_json::from_str(data)?;
+    value
+        .get("completion")
+        .and_then(|c| c.as_str())
+        .map(|s| s.to_owned())
+        .ok_or_else(|| Claude2Error::UnexpectedFormat(format!("unexpected event format: {}", data)))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Client;
+    use futures::StreamExt;
+
+    use super::{sse_completion_deltas, Client};
+
+    // a single network chunk can carry a whole frame plus the start of the
+    // next one, and a later chunk can complete a frame that was split
+    // mid-line; `sse_completion_deltas` must still yield one delta per frame.
+    #[tokio::test]
+    async fn test_sse_completion_deltas_across_split_and_multi_frame_chunks() {
+        let chunks: Vec<reqwest::Result<bytes::Bytes>> = vec![
+            Ok(bytes::Bytes::from_static(
+                b"data: {\"completion\": \"Hel\"}\n\ndata: {\"completion\":",
+            )),
+            Ok(bytes::Bytes::from_static(b" \"lo\"}\n\n")),
+        ];
+
+        let stream = sse_completion_deltas(futures::stream::iter(chunks));
+        futures::pin_mut!(stream);
+
+        let mut deltas = Vec::new();
+        while let Some(delta) = stream.next().await {
+            deltas.push(delta.expect("valid delta"));
+        }
+
+        assert_eq!(deltas, vec!["Hel".to_owned(), "lo".to_owned()]);
+    }
 
     #[tokio::test]
     async fn test_full_workflow() -> anyhow::Result<()> {
@@ -343,7 +477,7 @@ mod tests {
             .await?;
 
         let answer = client
-            .send_message(&new_conversation_id, "hello world", None)
+            .send_message(&new_conversation_id, "hello world", None, None)
             .await?;
         println!("response: {}", answer);
 
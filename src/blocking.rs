@@ -0,0 +1,389 @@
+// a blocking mirror of `crate::Client`, for callers embedding this crate in
+// CLI tools or other sync contexts that don't want to pull in a tokio
+// runtime just to list or rename conversations. URL/body construction is
+// shared with the async client via `crate::shared` so the two can't drift.
+use std::time::Duration;
+
+use reqwest::blocking::{self, multipart};
+use reqwest::{
+    header::{self, ACCEPT, ACCEPT_LANGUAGE, CONTENT_TYPE, COOKIE, HOST, REFERER, USER_AGENT},
+    Method, Proxy,
+};
+use secrecy::{ExposeSecret, Secret};
+
+use crate::{
+    config::{client_builder_with_proxies, decide_retry, RequestConfig, RetryDecision},
+    error::{Claude2Error, Result},
+    objects::{Conversation, History},
+    options::SendOptions,
+    shared,
+};
+
+pub struct Client {
+    pub cookie: Secret<String>,
+    pub proxys: Vec<Proxy>,
+    pub organization_id: String,
+    pub send_options: SendOptions,
+    pub request_config: RequestConfig,
+    base_header: header::HeaderMap,
+}
+
+impl std::fmt::Debug for Client {
+    // redact the cookie (and skip `base_header`, which also carries it) so
+    // an accidental `{:?}` logging call never leaks the session credential.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("cookie", &self.cookie)
+            .field("proxys", &self.proxys)
+            .field("organization_id", &self.organization_id)
+            .field("send_options", &self.send_options)
+            .field("request_config", &self.request_config)
+            .finish()
+    }
+}
+
+impl Client {
+    pub const NEW_CHAT_NAME: &'static str = "test";
+
+    pub fn try_new(cookie: &str, proxys: Vec<String>) -> Result<Self> {
+        let cookie = Secret::new(cookie.to_owned());
+
+        let mut headers: header::HeaderMap = header::HeaderMap::new();
+        headers.insert(HOST, "claude.ai".parse()?);
+        headers.insert(COOKIE, cookie.expose_secret().parse()?);
+        headers.insert(REFERER, "https://claude.ai/chats".parse()?);
+        headers.insert(CONTENT_TYPE, "application/json".parse()?);
+        headers.insert(ACCEPT, "*/*".parse()?);
+        headers.insert(USER_AGENT, "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/115.0.0.0 Safari/537.36 Edg/115.0.1901.183".parse()?);
+        headers.insert(
+            ACCEPT_LANGUAGE,
+            "en-US,en;q=0.9,zh-CN;q=0.8,zh;q=0.7,en-GB;q=0.6".parse()?,
+        );
+
+        let mut reqwest_proxies = Vec::with_capacity(proxys.len());
+        for proxy in &proxys {
+            match Proxy::all(proxy) {
+                Ok(p) => reqwest_proxies.push(p),
+                Err(e) => {
+                    println!("proxy {} is not supported, warning: {}", proxy, e);
+                }
+            }
+        }
+
+        let request_config = RequestConfig::default();
+
+        let content = request(
+            Method::GET,
+            shared::ORGANIZATIONS_URL,
+            &reqwest_proxies,
+            headers.clone(),
+            None,
+            None,
+            &request_config,
+        )?;
+        let organization_id = get_organization_id(&content)?;
+
+        let send_options = SendOptions {
+            timezone: iana_time_zone::get_timezone().unwrap_or_else(|_| "UTC".to_owned()),
+            ..SendOptions::default()
+        };
+
+        Ok(Self {
+            cookie,
+            proxys: reqwest_proxies,
+            organization_id,
+            send_options,
+            request_config,
+            base_header: headers,
+        })
+    }
+
+    pub fn reset_proxy(&mut self) {
+        self.proxys = vec![];
+    }
+
+    pub fn proxy(&mut self, proxy: &str) {
+        match Proxy::all(proxy) {
+            Ok(p) => self.proxys.push(p),
+            Err(e) => {
+                println!("proxy {} is not supported, warning: {}", proxy, e);
+            }
+        }
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.request_config.timeout = timeout;
+    }
+
+    pub fn set_max_retries(&mut self, max_retries: u32) {
+        self.request_config.max_retries = max_retries;
+    }
+
+    pub fn set_base_backoff(&mut self, base_backoff: Duration) {
+        self.request_config.base_backoff = base_backoff;
+    }
+
+    pub fn list_all_conversations(&self) -> Result<Vec<Conversation>> {
+        let content = request(
+            Method::GET,
+            &shared::chat_conversations_url(&self.organization_id),
+            &self.proxys,
+            self.base_header.clone(),
+            None,
+            None,
+            &self.request_config,
+        )?;
+
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    // add
+    pub fn create_chat_conversation(&self) -> Result<String> {
+        // format: 42ead3c7-4cb6-4599-a26f-e6e87b6d54db
+        let id = uuid::Uuid::new_v4().to_string();
+
+        request(
+            Method::POST,
+            &shared::chat_conversations_url(&self.organization_id),
+            &self.proxys,
+            self.base_header.clone(),
+            Some(shared::create_chat_conversation_body(
+                &id,
+                Self::NEW_CHAT_NAME,
+            )),
+            None,
+            &self.request_config,
+        )?;
+
+        Ok(id)
+    }
+
+    // delete
+    pub fn delete_chat_conversation(&self, conversation_id: &str) -> Result<()> {
+        request(
+            Method::DELETE,
+            &shared::chat_conversation_url(&self.organization_id, conversation_id),
+            &self.proxys,
+            self.base_header.clone(),
+            None,
+            None,
+            &self.request_config,
+        )?;
+
+        Ok(())
+    }
+
+    // modify
+    pub fn rename_chat_conversation(&self, conversation_id: &str, new_title: &str) -> Result<()> {
+        request(
+            Method::POST,
+            shared::RENAME_CHAT_URL,
+            &self.proxys,
+            self.base_header.clone(),
+            Some(shared::rename_chat_conversation_body(
+                &self.organization_id,
+                conversation_id,
+                new_title,
+            )),
+            None,
+            &self.request_config,
+        )?;
+
+        Ok(())
+    }
+
+    // modify
+    // upload attachment should accompany with a send_message request
+    pub fn upload_attachment(&self, filename: &str) -> Result<serde_json::Value> {
+        let file = std::fs::File::open(filename).map_err(|e| {
+            Claude2Error::UnexpectedFormat(format!("failed to open {}: {}", filename, e))
+        })?;
+
+        let file_name = std::path::Path::new(filename)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(filename)
+            .to_owned();
+        let mime_type = mime_guess::from_path(filename).first_or_octet_stream();
+
+        let some_file = multipart::Part::reader(file)
+            .file_name(file_name)
+            .mime_str(mime_type.as_ref())?;
+
+        let form = multipart::Form::new()
+            .text("orgUuid", self.organization_id.clone())
+            .part("file", some_file);
+
+        // the multipart form carries its own `Content-Type: multipart/
+        // form-data; boundary=...` header; the base header's `application/
+        // json` must not be sent in its place.
+        let mut headers = self.base_header.clone();
+        headers.remove(CONTENT_TYPE);
+
+        let content = request(
+            Method::POST,
+            shared::CONVERT_DOCUMENT_URL,
+            &self.proxys,
+            headers,
+            None,
+            Some(form),
+            &self.request_config,
+        )?;
+
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    // modify
+    // send message
+    pub fn send_message(
+        &self,
+        conversation_id: &str,
+        prompt: &str,
+        attachment: Option<&str>,
+        options: Option<&SendOptions>,
+    ) -> Result<serde_json::Value> {
+        let attachments = if let Some(attachment) = attachment {
+            let attachment = self.upload_attachment(attachment)?;
+            vec![attachment.to_string()]
+        } else {
+            vec![]
+        };
+
+        let bytes = request(
+            Method::POST,
+            shared::APPEND_MESSAGE_URL,
+            &self.proxys,
+            self.base_header.clone(),
+            Some(shared::send_message_body(
+                &self.organization_id,
+                conversation_id,
+                prompt,
+                &attachments,
+                options.unwrap_or(&self.send_options),
+            )),
+            None,
+            &self.request_config,
+        )?;
+
+        let s = std::str::from_utf8(&bytes)?;
+        let data = s
+            .trim()
+            .split('\n')
+            .last()
+            .ok_or_else(|| Claude2Error::UnexpectedFormat("empty response body".to_owned()))?;
+        let data = data
+            .strip_prefix("data: ")
+            .ok_or_else(|| Claude2Error::UnexpectedFormat(format!("unexpected line: {}", data)))?;
+
+        Ok(serde_json::from_str(data)?)
+    }
+
+    // query
+    pub fn chat_conversation_history(&self, conversation_id: &str) -> Result<History> {
+        let content = request(
+            Method::GET,
+            &shared::chat_conversation_url(&self.organization_id, conversation_id),
+            &self.proxys,
+            self.base_header.clone(),
+            None,
+            None,
+            &self.request_config,
+        )?;
+
+        Ok(serde_json::from_slice(&content)?)
+    }
+}
+
+fn get_organization_id(content: &[u8]) -> Result<String> {
+    let response: serde_json::Value = serde_json::from_slice(content)?;
+    response
+        .as_array()
+        .ok_or_else(|| Claude2Error::UnexpectedFormat("no organization info found".to_owned()))?[0]
+        .get("uuid")
+        .map(|s| s.as_str().unwrap_or("").to_owned())
+        .ok_or_else(|| Claude2Error::UnexpectedFormat("no organization id found".to_owned()))
+}
+
+fn request(
+    method: Method,
+    url: &str,
+    proxys: &[Proxy],
+    headers: header::HeaderMap,
+    body: Option<String>,
+    form: Option<multipart::Form>,
+    config: &RequestConfig,
+) -> Result<bytes::Bytes> {
+    // `multipart::Form` isn't `Clone`, so a request carrying one can't be
+    // rebuilt for a retry; send it once and surface whatever happens.
+    if let Some(form) = form {
+        return send_once(method, url, proxys, headers, body, Some(form), config.timeout);
+    }
+
+    let retryable = method == Method::GET;
+    let mut attempt = 0;
+
+    loop {
+        match send_once(
+            method.clone(),
+            url,
+            proxys,
+            headers.clone(),
+            body.clone(),
+            None,
+            config.timeout,
+        ) {
+            Ok(bytes) => return Ok(bytes),
+            Err(e) => match decide_retry(&e, retryable, attempt, config) {
+                RetryDecision::Retry(delay) => {
+                    log::debug!(
+                        "url:{:?} request failed ({}), retrying in {:?} (attempt {}/{})",
+                        url,
+                        e,
+                        delay,
+                        attempt + 1,
+                        config.max_retries
+                    );
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                RetryDecision::GiveUp => return Err(e),
+            },
+        }
+    }
+}
+
+fn send_once(
+    method: Method,
+    url: &str,
+    proxys: &[Proxy],
+    headers: header::HeaderMap,
+    body: Option<String>,
+    form: Option<multipart::Form>,
+    timeout: Duration,
+) -> Result<bytes::Bytes> {
+    let client_builder = client_builder_with_proxies(
+        proxys,
+        blocking::Client::builder()
+            .default_headers(headers)
+            .timeout(timeout),
+    );
+
+    let client = client_builder.build()?;
+    let mut req = client.request(method, url);
+    if let Some(body) = body {
+        req = req.body(body);
+    }
+    if let Some(form) = form {
+        req = req.multipart(form);
+    }
+
+    let response = req.send()?;
+    if let Some(e) = Claude2Error::from_status(response.status(), response.headers()) {
+        return Err(e);
+    }
+    let bytes = response.bytes()?;
+
+    log::debug!("url:{:?}, response:{:?}", url, String::from_utf8_lossy(&bytes));
+
+    Ok(bytes)
+}
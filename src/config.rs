@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::Proxy;
+
+use crate::error::Claude2Error;
+
+// tuning knobs for `utils::request`/`blocking::request`: how long to wait
+// for a response, and how hard to retry a transient failure before giving
+// up on a flaky proxy chain.
+#[derive(Debug, Clone)]
+pub struct RequestConfig {
+    pub timeout: Duration,
+    pub max_retries: u32,
+    pub base_backoff: Duration,
+}
+
+impl Default for RequestConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_retries: 3,
+            base_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+// reqwest's async and blocking `ClientBuilder`s are distinct types that both
+// happen to expose `.proxy(...)`; this lets `utils::request`/
+// `blocking::request` apply a proxy list through one shared helper instead
+// of two copies of the same loop.
+pub(crate) trait ProxyBuilder: Sized {
+    fn with_proxy(self, proxy: Proxy) -> Self;
+}
+
+impl ProxyBuilder for reqwest::ClientBuilder {
+    fn with_proxy(self, proxy: Proxy) -> Self {
+        self.proxy(proxy)
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl ProxyBuilder for reqwest::blocking::ClientBuilder {
+    fn with_proxy(self, proxy: Proxy) -> Self {
+        self.proxy(proxy)
+    }
+}
+
+pub(crate) fn client_builder_with_proxies<B: ProxyBuilder>(
+    proxys: &[Proxy],
+    mut client_builder: B,
+) -> B {
+    for proxy in proxys.iter() {
+        client_builder = client_builder.with_proxy(proxy.clone());
+    }
+    client_builder
+}
+
+fn backoff_with_jitter(base: Duration, attempt: u32) -> Duration {
+    let scale = 1u32 << attempt.min(16);
+    let exp = base.saturating_mul(scale);
+    let jitter_ms = rand::thread_rng().gen_range(0..=(exp.as_millis() as u64 / 4 + 1));
+    exp + Duration::from_millis(jitter_ms)
+}
+
+// what the retry loop in `utils::request`/`blocking::request` should do
+// after a failed attempt; factored out so the backoff formula and retry
+// conditions live in one place instead of drifting between the async and
+// blocking transports.
+pub(crate) enum RetryDecision {
+    Retry(Duration),
+    GiveUp,
+}
+
+pub(crate) fn decide_retry(
+    error: &Claude2Error,
+    retryable: bool,
+    attempt: u32,
+    config: &RequestConfig,
+) -> RetryDecision {
+    match error {
+        Claude2Error::RateLimited { retry_after } if attempt < config.max_retries => {
+            let delay = retry_after
+                .map(|s| Duration::from_secs(*s))
+                .unwrap_or_else(|| backoff_with_jitter(config.base_backoff, attempt));
+            RetryDecision::Retry(delay)
+        }
+        Claude2Error::Http(_) | Claude2Error::ServerError { .. }
+            if retryable && attempt < config.max_retries =>
+        {
+            RetryDecision::Retry(backoff_with_jitter(config.base_backoff, attempt))
+        }
+        _ => RetryDecision::GiveUp,
+    }
+}
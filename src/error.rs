@@ -0,0 +1,56 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Claude2Error {
+    #[error("http request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("invalid header value: {0}")]
+    InvalidHeader(#[from] reqwest::header::InvalidHeaderValue),
+
+    #[error("unauthorized: cookie is missing, expired, or invalid")]
+    Unauthorized,
+
+    #[error("rate limited by claude.ai{}", .retry_after.map(|s| format!(", retry after {s}s")).unwrap_or_default())]
+    RateLimited { retry_after: Option<u64> },
+
+    #[error("server error: {status}")]
+    ServerError { status: reqwest::StatusCode },
+
+    #[error("failed to parse response: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("response was not valid utf-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+
+    #[error("unexpected response format: {0}")]
+    UnexpectedFormat(String),
+}
+
+pub type Result<T> = std::result::Result<T, Claude2Error>;
+
+impl Claude2Error {
+    // classifies a response status that signals something other than a
+    // normal payload (expired cookie, rate limiting). shared by the async
+    // and blocking `request` helpers, whose `Response` types both expose
+    // compatible `status`/`headers` accessors.
+    pub(crate) fn from_status(
+        status: reqwest::StatusCode,
+        headers: &reqwest::header::HeaderMap,
+    ) -> Option<Self> {
+        match status {
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+                Some(Claude2Error::Unauthorized)
+            }
+            reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                let retry_after = headers
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse().ok());
+                Some(Claude2Error::RateLimited { retry_after })
+            }
+            status if status.is_server_error() => Some(Claude2Error::ServerError { status }),
+            _ => None,
+        }
+    }
+}
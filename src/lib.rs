@@ -0,0 +1,14 @@
+mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod config;
+mod error;
+pub mod objects;
+mod options;
+mod shared;
+mod utils;
+
+pub use api::Client;
+pub use config::RequestConfig;
+pub use error::{Claude2Error, Result};
+pub use options::{SendOptions, SendOptionsBuilder};
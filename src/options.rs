@@ -0,0 +1,67 @@
+// configuration for `Client::send_message`/`send_message_stream`: which
+// model to target, what timezone to report, and any extras the
+// `append_message` endpoint accepts.
+#[derive(Debug, Clone)]
+pub struct SendOptions {
+    pub model: String,
+    pub timezone: String,
+    pub system_prompt: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+impl Default for SendOptions {
+    fn default() -> Self {
+        Self {
+            model: "claude-2".to_owned(),
+            timezone: "UTC".to_owned(),
+            system_prompt: None,
+            metadata: None,
+        }
+    }
+}
+
+impl SendOptions {
+    pub fn builder() -> SendOptionsBuilder {
+        SendOptionsBuilder::default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SendOptionsBuilder {
+    model: Option<String>,
+    timezone: Option<String>,
+    system_prompt: Option<String>,
+    metadata: Option<serde_json::Value>,
+}
+
+impl SendOptionsBuilder {
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn timezone(mut self, timezone: impl Into<String>) -> Self {
+        self.timezone = Some(timezone.into());
+        self
+    }
+
+    pub fn system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+
+    pub fn metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    pub fn build(self) -> SendOptions {
+        let defaults = SendOptions::default();
+        SendOptions {
+            model: self.model.unwrap_or(defaults.model),
+            timezone: self.timezone.unwrap_or(defaults.timezone),
+            system_prompt: self.system_prompt,
+            metadata: self.metadata,
+        }
+    }
+}
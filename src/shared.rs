@@ -0,0 +1,74 @@
+// URL and JSON-body builders shared between the async `Client` (api.rs) and
+// its blocking mirror (blocking.rs), so the two front-ends can't drift apart.
+use serde_json::json;
+
+use crate::options::SendOptions;
+
+pub(crate) const ORGANIZATIONS_URL: &str = "https://claude.ai/api/organizations";
+pub(crate) const RENAME_CHAT_URL: &str = "https://claude.ai/api/rename_chat";
+pub(crate) const APPEND_MESSAGE_URL: &str = "https://claude.ai/api/append_message";
+pub(crate) const CONVERT_DOCUMENT_URL: &str = "https://claude.ai/api/convert_document";
+
+pub(crate) fn chat_conversations_url(organization_id: &str) -> String {
+    format!(
+        "https://claude.ai/api/organizations/{}/chat_conversations",
+        organization_id
+    )
+}
+
+pub(crate) fn chat_conversation_url(organization_id: &str, conversation_id: &str) -> String {
+    format!(
+        "https://claude.ai/api/organizations/{}/chat_conversations/{}",
+        organization_id, conversation_id
+    )
+}
+
+pub(crate) fn create_chat_conversation_body(id: &str, name: &str) -> String {
+    json!({
+        "uuid": id,
+        "name": name,
+    })
+    .to_string()
+}
+
+pub(crate) fn rename_chat_conversation_body(
+    organization_id: &str,
+    conversation_id: &str,
+    new_title: &str,
+) -> String {
+    json!({
+        "organization_uuid": organization_id,
+        "conversation_uuid": conversation_id,
+        "title": new_title
+    })
+    .to_string()
+}
+
+pub(crate) fn send_message_body(
+    organization_id: &str,
+    conversation_id: &str,
+    prompt: &str,
+    attachments: &[String],
+    options: &SendOptions,
+) -> String {
+    let mut completion = json!({
+        "prompt": prompt,
+        "timezone": options.timezone,
+        "model": options.model,
+    });
+    if let Some(system_prompt) = &options.system_prompt {
+        completion["system_prompt"] = json!(system_prompt);
+    }
+    if let Some(metadata) = &options.metadata {
+        completion["metadata"] = metadata.clone();
+    }
+
+    json!({
+        "completion": completion,
+        "organization_uuid": organization_id,
+        "conversation_uuid": conversation_id,
+        "text": prompt,
+        "attachments": attachments
+    })
+    .to_string()
+}
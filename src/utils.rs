@@ -1,15 +1,18 @@
 use std::time::Duration;
 
-use reqwest::{ClientBuilder, Proxy, Method, header::HeaderMap, multipart};
-use anyhow::Result;
-pub(crate) fn client_builder_with_proxies(
-    proxys: &[Proxy],
-    mut client_builder: ClientBuilder,
-) -> ClientBuilder {
-    for proxy in proxys.iter() {
-        client_builder = client_builder.proxy(proxy.clone());
+use futures::Stream;
+use reqwest::{header::HeaderMap, multipart, Method, Proxy};
+
+use crate::{
+    config::{client_builder_with_proxies, decide_retry, RequestConfig, RetryDecision},
+    error::{Claude2Error, Result},
+};
+
+fn check_status(response: &reqwest::Response) -> Result<()> {
+    match Claude2Error::from_status(response.status(), response.headers()) {
+        Some(e) => Err(e),
+        None => Ok(()),
     }
-    client_builder
 }
 
 pub(crate) async fn request(
@@ -19,12 +22,63 @@ pub(crate) async fn request(
     headers: HeaderMap,
     body: Option<String>,
     form: Option<multipart::Form>,
+    config: &RequestConfig,
+) -> Result<bytes::Bytes> {
+    // `multipart::Form` isn't `Clone`, so a request carrying one can't be
+    // rebuilt for a retry; send it once and surface whatever happens.
+    if let Some(form) = form {
+        return send_once(method, url, proxys, headers, body, Some(form), config.timeout).await;
+    }
+
+    let retryable = method == Method::GET;
+    let mut attempt = 0;
+
+    loop {
+        match send_once(
+            method.clone(),
+            url,
+            proxys,
+            headers.clone(),
+            body.clone(),
+            None,
+            config.timeout,
+        )
+        .await
+        {
+            Ok(bytes) => return Ok(bytes),
+            Err(e) => match decide_retry(&e, retryable, attempt, config) {
+                RetryDecision::Retry(delay) => {
+                    log::debug!(
+                        "url:{:?} request failed ({}), retrying in {:?} (attempt {}/{})",
+                        url,
+                        e,
+                        delay,
+                        attempt + 1,
+                        config.max_retries
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                RetryDecision::GiveUp => return Err(e),
+            },
+        }
+    }
+}
+
+async fn send_once(
+    method: Method,
+    url: &str,
+    proxys: &[Proxy],
+    headers: HeaderMap,
+    body: Option<String>,
+    form: Option<multipart::Form>,
+    timeout: Duration,
 ) -> Result<bytes::Bytes> {
     let client_builder = client_builder_with_proxies(
         proxys,
         reqwest::Client::builder()
             .default_headers(headers)
-            .timeout(Duration::from_secs(10)),
+            .timeout(timeout),
     );
 
     let client = client_builder.build()?;
@@ -35,13 +89,42 @@ pub(crate) async fn request(
     if let Some(form) = form {
         req = req.multipart(form);
     }
-    let res = req.send().await?.bytes().await.map_err(Into::into);
 
-    log::debug!(
-        "url:{:?}, response:{:?}",
-        url,
-        String::from_utf8_lossy(&res.as_ref().unwrap_or(&bytes::Bytes::default()))
+    let response = req.send().await?;
+    check_status(&response)?;
+    let bytes = response.bytes().await?;
+
+    log::debug!("url:{:?}, response:{:?}", url, String::from_utf8_lossy(&bytes));
+
+    Ok(bytes)
+}
+
+// like `request`, but returns the raw `bytes_stream` instead of buffering the
+// whole body, for endpoints that respond with `text/event-stream`.
+pub(crate) async fn request_stream(
+    method: Method,
+    url: &str,
+    proxys: &[Proxy],
+    headers: HeaderMap,
+    body: Option<String>,
+    config: &RequestConfig,
+) -> Result<impl Stream<Item = reqwest::Result<bytes::Bytes>>> {
+    let client_builder = client_builder_with_proxies(
+        proxys,
+        reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(config.timeout),
     );
 
-    res
-}
\ No newline at end of file
+    let client = client_builder.build()?;
+    let mut req = client.request(method, url);
+    if let Some(body) = body {
+        req = req.body(body);
+    }
+
+    log::debug!("url:{:?}, streaming request", url);
+
+    let response = req.send().await?;
+    check_status(&response)?;
+    Ok(response.bytes_stream())
+}